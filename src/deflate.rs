@@ -0,0 +1,244 @@
+extern crate flate2;
+
+use std::cmp;
+
+use flate2::{Compress, Decompress, Compression, Flush, Status};
+
+use super::{WampResult, WampError};
+
+/// The name of the extension in the `Sec-WebSocket-Extensions` header
+const PERMESSAGE_DEFLATE: &'static str = "permessage-deflate";
+/// Every compressed message ends with an empty stored block, which is left off on the wire
+const DEFLATE_TAIL: [u8; 4] = [0x00, 0x00, 0xFF, 0xFF];
+
+/// Settings for the [permessage-deflate](https://tools.ietf.org/html/rfc7692) WebSocket extension,
+/// see `Client::compression`
+///
+/// We always compress with the largest window, so only the router's window can be limited.
+#[derive(Debug, Clone)]
+pub struct DeflateConfig {
+    /// Ask the router to compress with a window of at most 2^bits bytes, between 8 and 15
+    pub server_max_window_bits: Option<u8>,
+    /// Ask the router to compress every message on its own, which saves memory on both ends
+    pub server_no_context_takeover: bool,
+    /// Messages shorter than this many bytes are sent uncompressed
+    pub threshold: usize,
+}
+
+impl Default for DeflateConfig {
+    fn default() -> Self {
+        DeflateConfig {
+            server_max_window_bits: None,
+            server_no_context_takeover: false,
+            threshold: 1024,
+        }
+    }
+}
+
+impl DeflateConfig {
+    /// The value of the `Sec-WebSocket-Extensions` header offering the extension
+    pub fn offer(&self) -> String {
+        let mut offer = PERMESSAGE_DEFLATE.to_string();
+        if self.server_no_context_takeover {
+            offer.push_str("; server_no_context_takeover");
+        }
+        if let Some(bits) = self.server_max_window_bits {
+            offer.push_str(&format!("; server_max_window_bits={}", bits));
+        }
+        offer
+    }
+
+    /// Reads the extension parameters the router agreed to from the `Sec-WebSocket-Extensions`
+    /// header of its response, `None` if it declined to compress
+    pub fn negotiate(&self, response: &[String]) -> WampResult<Option<(Deflater, Inflater)>> {
+        let extension = response.iter()
+            .flat_map(|header| header.split(','))
+            .map(|extension| extension.split(';').map(|param| param.trim()).collect::<Vec<_>>())
+            .find(|params| params[0] == PERMESSAGE_DEFLATE);
+        let params = match extension {
+            Some(params) => params,
+            None => return Ok(None),
+        };
+
+        let mut client_no_context_takeover = false;
+        let mut server_no_context_takeover = false;
+        for param in &params[1..] {
+            let mut parts = param.splitn(2, '=');
+            match (parts.next().unwrap_or(""), parts.next()) {
+                ("client_no_context_takeover", None) => client_no_context_takeover = true,
+                ("server_no_context_takeover", None) => server_no_context_takeover = true,
+                ("server_max_window_bits", Some(bits)) => match bits.trim_matches('"').parse::<u8>() {
+                    Ok(bits) if bits >= 8 && bits <= 15 => (),
+                    _ => return Err(WampError::ProtocolError),
+                },
+                // We did not offer client_max_window_bits, so the router may not limit our window
+                _ => return Err(WampError::ProtocolError),
+            }
+        }
+
+        Ok(Some((Deflater::new(self.threshold, client_no_context_takeover), Inflater::new(server_no_context_takeover))))
+    }
+}
+
+/// Compresses the messages we send
+pub struct Deflater {
+    compress: Compress,
+    threshold: usize,
+    no_context_takeover: bool,
+}
+
+impl Deflater {
+    fn new(threshold: usize, no_context_takeover: bool) -> Self {
+        Deflater {
+            compress: Compress::new(Compression::Default, false),
+            threshold: threshold,
+            no_context_takeover: no_context_takeover,
+        }
+    }
+
+    /// Compresses a message, `None` if it is too short to be worth it
+    pub fn deflate(&mut self, message: &[u8]) -> Option<Vec<u8>> {
+        if message.len() < self.threshold {
+            return None;
+        }
+        if self.no_context_takeover {
+            self.compress = Compress::new(Compression::Default, false);
+        }
+
+        let mut output = Vec::with_capacity(message.len() / 2 + DEFLATE_TAIL.len());
+        let start = self.compress.total_in();
+        loop {
+            let (total_in, total_out) = (self.compress.total_in(), output.len());
+            let _ = self.compress.compress_vec(&message[(total_in - start) as usize..], &mut output, Flush::Sync);
+            let has_space = output.len() < output.capacity();
+            // The flush is complete once all input is consumed and there is output space left
+            if (self.compress.total_in() - start) as usize == message.len() && has_space {
+                break;
+            }
+            if self.compress.total_in() == total_in && output.len() == total_out && has_space {
+                // Stuck, send this one uncompressed. The router never saw this context, so starting over is fine
+                self.compress = Compress::new(Compression::Default, false);
+                return None;
+            }
+            if !has_space {
+                let grow = output.capacity();
+                output.reserve(grow);
+            }
+        }
+
+        if output.ends_with(&DEFLATE_TAIL) {
+            let len = output.len() - DEFLATE_TAIL.len();
+            output.truncate(len);
+        }
+        Some(output)
+    }
+}
+
+/// Decompresses the messages we receive
+pub struct Inflater {
+    decompress: Decompress,
+    no_context_takeover: bool,
+}
+
+impl Inflater {
+    fn new(no_context_takeover: bool) -> Self {
+        Inflater {
+            decompress: Decompress::new(false),
+            no_context_takeover: no_context_takeover,
+        }
+    }
+
+    /// Decompresses a message, failing with `WampError::PayloadTooLarge` if it inflates
+    /// to more than `max_size` bytes and with `WampError::ProtocolError` if it is corrupt
+    pub fn inflate(&mut self, message: &[u8], max_size: usize) -> WampResult<Vec<u8>> {
+        // Even an empty message compresses to at least one byte
+        if message.is_empty() {
+            return Err(WampError::ProtocolError);
+        }
+        if self.no_context_takeover {
+            self.decompress = Decompress::new(false);
+        }
+
+        let mut input = message.to_vec();
+        input.extend_from_slice(&DEFLATE_TAIL);
+        let mut output = Vec::with_capacity(cmp::max(message.len() * 2, 1));
+        let start = self.decompress.total_in();
+        loop {
+            let (total_in, total_out) = (self.decompress.total_in(), output.len());
+            let status = try!(self.decompress.decompress_vec(&input[(total_in - start) as usize..], &mut output, Flush::Sync)
+                .map_err(|_| WampError::ProtocolError));
+            if output.len() > max_size {
+                return Err(WampError::PayloadTooLarge);
+            }
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let has_space = output.len() < output.capacity();
+            match status {
+                Status::StreamEnd => {
+                    // Nothing but the empty block we appended may follow a final block
+                    if consumed < message.len() {
+                        return Err(WampError::ProtocolError);
+                    }
+                    self.decompress = Decompress::new(false);
+                    return Ok(output);
+                },
+                _ if consumed == input.len() && has_space => return Ok(output),
+                _ if self.decompress.total_in() == total_in && output.len() == total_out && has_space => {
+                    return Err(WampError::ProtocolError);
+                },
+                _ => (),
+            }
+            if !has_space {
+                let grow = output.capacity();
+                output.reserve(grow);
+            }
+        }
+    }
+}
+
+#[test]
+fn deflate_negotiate() {
+    let config = DeflateConfig { server_max_window_bits: Some(10), server_no_context_takeover: true, .. DeflateConfig::default() };
+    assert!(config.offer() == "permessage-deflate; server_no_context_takeover; server_max_window_bits=10");
+
+    assert!(config.negotiate(&[]).unwrap().is_none());
+    assert!(config.negotiate(&["permessage-deflate; server_no_context_takeover; server_max_window_bits=10".to_string()]).unwrap().is_some());
+    assert!(config.negotiate(&["x-webkit-deflate-frame, permessage-deflate".to_string()]).unwrap().is_some());
+    assert!(config.negotiate(&["permessage-deflate; client_max_window_bits=9".to_string()]).is_err());
+    assert!(config.negotiate(&["permessage-deflate; server_max_window_bits=16".to_string()]).is_err());
+}
+
+#[test]
+fn deflate_round_trip() {
+    let config = DeflateConfig { threshold: 16, .. DeflateConfig::default() };
+    let (mut deflater, mut inflater) = config.negotiate(&["permessage-deflate".to_string()]).unwrap().unwrap();
+
+    assert!(deflater.deflate(b"[16,1,{},\"a\"]").is_none());
+    let message = "[16,1,{},\"com.myapp.telemetry\",[],{\"samples\":[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}]".repeat(100);
+    // The second message reuses the context of the first
+    for _ in 0..2 {
+        let compressed = deflater.deflate(message.as_bytes()).unwrap();
+        assert!(compressed.len() < message.len() / 10);
+        assert!(inflater.inflate(&compressed, message.len()).unwrap() == message.as_bytes());
+    }
+
+    let compressed = deflater.deflate(message.as_bytes()).unwrap();
+    assert!(inflater.inflate(&compressed, message.len() - 1).is_err());
+}
+
+#[test]
+fn deflate_corrupt() {
+    let config = DeflateConfig::default();
+    let (_, mut inflater) = config.negotiate(&["permessage-deflate".to_string()]).unwrap().unwrap();
+    assert!(inflater.inflate(&[], 1024).is_err());
+
+    // A final block holding "hi", followed by more data
+    let mut compress = Compress::new(Compression::Default, false);
+    let mut message = Vec::with_capacity(64);
+    compress.compress_vec(b"hi", &mut message, Flush::Finish);
+    assert!(inflater.inflate(&message, 1024).unwrap() == b"hi");
+    message.extend_from_slice(b"more");
+    assert!(inflater.inflate(&message, 1024).is_err());
+
+    // A block that is cut off
+    assert!(inflater.inflate(&[0x02], 1024).is_err());
+}
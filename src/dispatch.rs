@@ -0,0 +1,138 @@
+use std::any::Any;
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, mpsc};
+use std::thread;
+use std::panic::{self, AssertUnwindSafe};
+
+use message::Payload;
+use WampError;
+
+/// A subscription callback. Each one has its own lock, so it can be called without holding
+/// the session's locks and never runs on two threads at once.
+pub type Callback = Arc<Mutex<Box<Fn(&Payload) + Send>>>;
+
+/// Receives the errors that can't be returned to the caller, see `Session::on_error`
+pub type ErrorHandler = Arc<Mutex<Option<Arc<Fn(WampError) + Send + Sync>>>>;
+
+/// Calls the error handler without holding its lock, so that it may replace itself,
+/// and catches its panics so that it can't take down the thread reporting the error.
+/// `context` prefixes what is logged, e.g. the realm and URL of the session.
+pub fn report(handler: &ErrorHandler, context: &str, error: WampError) {
+    let handler = handler.lock().unwrap().clone();
+    match handler {
+        Some(handler) => {
+            if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| handler(error))) {
+                error!("[{}] The error handler panicked {:?}", context, panic_message(panic));
+            }
+        },
+        None => error!("[{}] Unhandled error {:?}", context, error),
+    }
+}
+
+/// Where subscription callbacks run, see `Client::dispatch`
+///
+/// Whatever the choice, the events of a subscription are handled one at a time and in the order
+/// they were published.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Dispatch {
+    /// On the thread receiving from the router, so a slow callback holds up every other message
+    Inline,
+    /// On a thread of its own for every subscribed topic
+    ThreadPerSubscription,
+    /// On a pool of this many threads shared by all topics, each topic always uses the same thread
+    Pool(usize),
+}
+
+impl Default for Dispatch {
+    fn default() -> Self {
+        Dispatch::Inline
+    }
+}
+
+/// A topic and its callbacks along with the event to call them with
+type Job = (String, Vec<Callback>, Payload);
+
+/// Runs the callbacks of a session as configured with `Dispatch`.
+/// A callback that panics is reported to the error handler, the session carries on.
+pub struct Dispatcher {
+    mode: Dispatch,
+    errors: ErrorHandler,
+    /// Prefixes what is logged about the errors, see `report`
+    context: String,
+    /// The queues of the worker threads, by topic for `ThreadPerSubscription`
+    topics: Mutex<HashMap<String, mpsc::Sender<Job>>>,
+    /// The queues of the worker threads for `Pool`
+    pool: Mutex<Vec<mpsc::Sender<Job>>>,
+}
+
+impl Dispatcher {
+    pub fn new(mode: Dispatch, errors: ErrorHandler, context: String) -> Self {
+        let pool = match mode {
+            Dispatch::Pool(size) => (0..cmp::max(size, 1)).map(|_| spawn_worker(errors.clone(), context.clone())).collect(),
+            _ => Vec::new(),
+        };
+        Dispatcher {
+            mode: mode,
+            errors: errors,
+            context: context,
+            topics: Mutex::new(HashMap::new()),
+            pool: Mutex::new(pool),
+        }
+    }
+
+    /// Calls the callbacks of `topic` with `payload`, now or on a worker thread
+    pub fn dispatch(&self, topic: &str, callbacks: Vec<Callback>, payload: Payload) {
+        match self.mode {
+            Dispatch::Inline => run(&self.errors, &self.context, topic, &callbacks, &payload),
+            Dispatch::ThreadPerSubscription => {
+                let mut topics = self.topics.lock().unwrap();
+                let (errors, context) = (&self.errors, &self.context);
+                let worker = topics.entry(topic.to_string()).or_insert_with(|| spawn_worker(errors.clone(), context.clone()));
+                let _ = worker.send((topic.to_string(), callbacks, payload));
+            },
+            Dispatch::Pool(_) => {
+                let mut hasher = DefaultHasher::new();
+                topic.hash(&mut hasher);
+                let pool = self.pool.lock().unwrap();
+                let _ = pool[hasher.finish() as usize % pool.len()].send((topic.to_string(), callbacks, payload));
+            },
+        }
+    }
+}
+
+/// Starts a thread running jobs in order until the dispatcher is dropped
+fn spawn_worker(errors: ErrorHandler, context: String) -> mpsc::Sender<Job> {
+    let (tx, rx) = mpsc::channel::<Job>();
+    thread::spawn(move || {
+        for (topic, callbacks, payload) in rx.iter() {
+            run(&errors, &context, &topic, &callbacks, &payload);
+        }
+    });
+    tx
+}
+
+fn run(errors: &ErrorHandler, context: &str, topic: &str, callbacks: &[Callback], payload: &Payload) {
+    for callback in callbacks {
+        let callback = callback.lock().unwrap();
+        // Catch the panic before the lock is released so that the callback can be called again
+        if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(|| callback(payload))) {
+            report(errors, context, WampError::HandlerPanic {
+                topic: topic.to_string(),
+                message: panic_message(panic),
+            });
+        }
+    }
+}
+
+pub fn panic_message(panic: Box<Any + Send>) -> String {
+    match panic.downcast::<String>() {
+        Ok(message) => *message,
+        Err(panic) => match panic.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic".to_string(),
+        },
+    }
+}
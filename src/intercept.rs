@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use std::panic::{self, AssertUnwindSafe};
+
+use rustc_serialize::Encodable;
+use rustc_serialize::json::{self, Json};
+
+use super::{WampResult, WampError};
+use dispatch::{ErrorHandler, report, panic_message};
+
+/// Which way a message is going, see `Session::intercept`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Direction {
+    /// From us to the router, before it is handed to the transport
+    Outgoing,
+    /// From the router to us, before it is handled
+    Incoming,
+}
+
+/// Sees a message and may change it, returns `false` to drop it
+pub type Interceptor = Arc<Fn(Direction, &mut Json) -> bool + Send + Sync>;
+
+/// Runs `message` through the interceptors in the order they were added,
+/// `false` as soon as one of them drops it. An interceptor that panics drops it too,
+/// the panic is reported to `errors` with `context` prefixing what is logged.
+pub fn run(interceptors: &[Interceptor], direction: Direction, message: &mut Json, errors: &ErrorHandler, context: &str) -> bool {
+    interceptors.iter().all(|interceptor| {
+        match panic::catch_unwind(AssertUnwindSafe(|| interceptor(direction, message))) {
+            Ok(keep) => keep,
+            Err(panic) => {
+                report(errors, context, WampError::InterceptorPanic {
+                    direction: direction,
+                    message: panic_message(panic),
+                });
+                false
+            },
+        }
+    })
+}
+
+/// The JSON form of a message we are about to send
+pub fn to_json<T: Encodable>(message: &T) -> WampResult<Json> {
+    json::encode(message).ok()
+        .and_then(|raw| Json::from_str(&raw).ok())
+        .ok_or(WampError::ProtocolError)
+}
@@ -13,7 +13,7 @@
 //! use std::collections::HashMap;
 //!
 //! // connect to a local router at the realm "realm1"
-//! let mut session = Client::new("ws://localhost:8080/ws", "realm1").connect().unwrap(); 
+//! let session = Client::new("ws://localhost:8080/ws", "realm1").unwrap().connect().unwrap(); 
 //!
 //! let mut kwarg_map = HashMap::new();
 //! kwarg_map.insert("some_key", "some_value");
@@ -24,7 +24,7 @@
 //!
 //! // publish the positional args (42, "hello from rust!") and the keyword argument
 //! // {"some_key": "some_value", "another_key", "another_value"} on the sample topic URI
-//! session.publish("com.myapp.topic1", args, kwargs); 
+//! session.publish("com.myapp.topic1", args, kwargs).unwrap(); 
 //! 
 //! // If we wanted to write a complementary client to receive the published argument,
 //! // we can use the following code ...
@@ -46,36 +46,99 @@
 //!     // we can do some other work with this function...
 //! };
 //!                                         
-//! session.subscribe("com.myapp.topic2", callback);
+//! session.subscribe("com.myapp.topic2", callback).unwrap();
 //! ```  
 //!
 
 extern crate rustc_serialize;
 extern crate websocket;
+extern crate openssl;
 extern crate crossbeam;
 extern crate rand;
+extern crate flate2;
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate log;
 
 pub mod client;
+pub mod uri;
+pub mod loopback;
 mod options;
 mod transport;
+mod tls;
+mod deflate;
+mod stream;
+mod dispatch;
+mod intercept;
 mod message;
 
 use std::result;
+use std::io;
 use websocket::result::WebSocketError;
+use openssl::ssl::error::SslError;
 use std::sync::mpsc::SendError;
 use rustc_serialize::json;
 
 // Some re-exports
 pub use message::WampType;
 pub use message::Payload;
+pub use message::MessageType;
+pub use options::{PublishOptions, EventDetails};
+pub use tls::TlsConfig;
+pub use deflate::DeflateConfig;
+pub use stream::{EventStream, Overflow};
+pub use dispatch::Dispatch;
+pub use intercept::Direction;
 
 #[derive(Debug)]
 pub enum WampError {
     InvalidURL,
+    /// A realm, topic or procedure is not a valid WAMP URI
+    InvalidUri(String),
     WebSocketError(WebSocketError),
+    IoError(io::Error),
+    TlsError(SslError),
+    /// The router refused the transport handshake
+    HandshakeRejected(String),
+    /// The HTTP proxy refused to open a tunnel, with the status line of its response
+    ProxyRejected(String),
     InternalThreadError,
+    /// The transport to the router is not connected
+    NotConnected,
+    /// Nothing arrived from the router in time
+    Timeout,
+    /// The message is larger than the transport allows, see `Client::max_message_size`
+    PayloadTooLarge,
     ProtocolError,
+    /// An interceptor dropped the message instead of sending it, see `Session::intercept`
+    Vetoed,
     DecodeError (json::DecoderError),
+    /// A subscription callback panicked, the session carries on
+    HandlerPanic {
+        /// The topic the callback was subscribed to
+        topic: String,
+        /// The message the callback panicked with
+        message: String,
+    },
+    /// An interceptor panicked, the message it was given is dropped
+    InterceptorPanic {
+        /// Which way the message was going
+        direction: Direction,
+        /// The message the interceptor panicked with
+        message: String,
+    },
+    /// The router answered a request with an ERROR message
+    Remote {
+        /// The type of the request that failed, e.g. `MessageType::SUBSCRIBE`
+        request_type: MessageType,
+        /// The error URI, see the `uri` module for those predefined by WAMP
+        uri: String,
+        /// Positional arguments sent with the error
+        args: Vec<json::Json>,
+        /// Keyword arguments sent with the error
+        kwargs: Option<json::Json>,
+    },
 }
 
 //impl From<ParseError> for WampError {
@@ -96,4 +159,16 @@ impl From<WebSocketError> for WampError {
     }
 }
 
+impl From<io::Error> for WampError {
+    fn from(err: io::Error) -> WampError {
+        WampError::IoError(err)
+    }
+}
+
+impl From<SslError> for WampError {
+    fn from(err: SslError) -> WampError {
+        WampError::TlsError(err)
+    }
+}
+
 pub type WampResult<T> = result::Result<T, WampError>;
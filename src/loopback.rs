@@ -0,0 +1,151 @@
+//! An in-memory transport, so WAMP components can be tested without a router or network.
+//!
+//! A `Peer` listens on a `memory://` URL and plays the part of the router: it receives every
+//! message the `Session` sends and delivers messages of its own to the `Session`. Messages sent
+//! by the peer are handled synchronously, so by the time `Peer::send` returns any callbacks
+//! they trigger have run.
+//!
+//! # Examples
+//!
+//! ```
+//! use rump::client::Client;
+//! use rump::loopback::Peer;
+//!
+//! let router = Peer::listen("memory://example").unwrap();
+//! let session = Client::new("memory://example", "realm1").unwrap().connect().unwrap();
+//!
+//! // the session greets the router first
+//! let hello = router.recv().unwrap();
+//! assert!(hello.starts_with("[1,\"realm1\""));
+//! router.send("[2,9129137332,{}]").unwrap();
+//! ```
+
+use std::sync::{Arc, Mutex, mpsc};
+use std::collections::HashMap;
+
+use rustc_serialize::Encodable;
+use websocket::Message;
+
+use transport::{WampConnector, WampSender, TransportConfig, Serializer};
+use WampError;
+use WampResult;
+
+type Handler = Arc<Mutex<Option<Box<Fn(Message) + Send>>>>;
+
+/// A `Peer` waiting for a `Session` to connect
+struct Listener {
+    to_peer: mpsc::Sender<String>,
+    handler: Handler,
+}
+
+lazy_static! {
+    static ref LISTENERS: Mutex<HashMap<String, Listener>> = Mutex::new(HashMap::new());
+}
+
+/// The router side of an in-memory connection, see the module documentation
+pub struct Peer {
+    url: String,
+    from_session: mpsc::Receiver<String>,
+    handler: Handler,
+}
+
+impl Peer {
+    /// Listen for a single `Session` connecting to `url`, which must start with `memory://`
+    pub fn listen(url: &str) -> WampResult<Peer> {
+        if !url.starts_with("memory://") {
+            return Err(WampError::InvalidURL);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let handler : Handler = Arc::new(Mutex::new(None));
+        let listener = Listener { to_peer: tx, handler: handler.clone() };
+
+        let mut listeners = LISTENERS.lock().unwrap();
+        if listeners.contains_key(url) {
+            return Err(WampError::InvalidURL);
+        }
+        listeners.insert(url.to_string(), listener);
+
+        Ok(Peer {
+            url: url.to_string(),
+            from_session: rx,
+            handler: handler,
+        })
+    }
+
+    /// Deliver a serialized message to the `Session`, running its handlers on this thread
+    pub fn send(&self, message: &str) -> WampResult<()> {
+        match *self.handler.lock().unwrap() {
+            Some(ref on_message) => {
+                on_message(Message::text(message.to_string()));
+                Ok(())
+            },
+            None => Err(WampError::InternalThreadError),
+        }
+    }
+
+    /// Close the connection, as if the router went away
+    pub fn close(&self) {
+        if let Some(on_message) = self.handler.lock().unwrap().take() {
+            on_message(Message::close());
+        }
+    }
+
+    /// Block until the `Session` sends a message, returning it serialized
+    pub fn recv(&self) -> WampResult<String> {
+        self.from_session.recv().map_err(|_| WampError::InternalThreadError)
+    }
+
+    /// Returns a message from the `Session` if one was sent, without blocking
+    pub fn try_recv(&self) -> Option<String> {
+        self.from_session.try_recv().ok()
+    }
+}
+
+impl Drop for Peer {
+    fn drop(&mut self) {
+        // Stop listening if no session connected
+        let mut listeners = LISTENERS.lock().unwrap();
+        let ours = listeners.get(&self.url).map_or(false, |l| Arc::ptr_eq(&l.handler, &self.handler));
+        if ours {
+            listeners.remove(&self.url);
+        }
+    }
+}
+
+/// The `Session` side of an in-memory connection, used for `memory://` URLs
+pub struct Loopback {
+    sender: mpsc::Sender<String>,
+    serializer: Serializer,
+    max_message_size: usize,
+}
+
+impl WampConnector for Loopback {
+    fn connect<F>(url: String, config: &TransportConfig, serializer: Serializer, on_message: F) -> WampResult<Self>
+        where F: 'static + Fn(Message) + Send {
+        let listener = try!(LISTENERS.lock().unwrap().remove(&url).ok_or(WampError::InvalidURL));
+        *listener.handler.lock().unwrap() = Some(Box::new(on_message));
+
+        Ok(Loopback {
+            sender: listener.to_peer,
+            serializer: serializer,
+            max_message_size: config.message_size_limit(),
+        })
+    }
+}
+
+impl WampSender for Loopback {
+    fn send<T: Encodable>(&self, message: &T) -> WampResult<()> {
+        let event = self.serializer.encode(message);
+        if event.payload.len() > self.max_message_size {
+            return Err(WampError::PayloadTooLarge);
+        }
+        let text = try!(String::from_utf8(event.payload.into_owned()).map_err(|_| WampError::ProtocolError));
+        try!(self.sender.send(text));
+        Ok(())
+    }
+
+    fn close(&self) {
+        // The peer notices once the `Loopback` is dropped, `Peer::recv` fails from then on
+    }
+}
@@ -1,14 +1,14 @@
 extern crate rustc_serialize;
-extern crate rand;
 
-use rand::Rng;
 use rustc_serialize::{Encodable, Encoder, Decodable, Decoder};
+use rustc_serialize::json::Json;
 
-use options::{Options, Details};
+use options::{Options, Details, EventDetails, ErrorDetails, WelcomeDetails, GoodbyeDetails};
 use transport::Serializer;
 
 use std::result;
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 use WampError;
 use WampResult;
@@ -81,6 +81,21 @@ impl From<u32> for MessageType {
 
 //#[derive(Debug)]
 pub enum WampEvent {
+    Welcome {
+        message_type: MessageType,
+        session_id: u64,
+        details: WelcomeDetails,
+    },
+    Abort {
+        message_type: MessageType,
+        details: GoodbyeDetails,
+        reason: String,
+    },
+    Goodbye {
+        message_type: MessageType,
+        details: GoodbyeDetails,
+        reason: String,
+    },
     Subscribed {
         message_type: MessageType,
         event_id: u64,
@@ -90,9 +105,16 @@ pub enum WampEvent {
         message_type: MessageType,
         topic_id: u64,
         event_id: u64,
-        options: Options,
+        details: EventDetails,
         has_kwargs: bool,
     },
+    Error {
+        message_type: MessageType,
+        request_type: MessageType,
+        request_id: u64,
+        details: ErrorDetails,
+        uri: String,
+    },
 }
 
 impl Decodable for WampEvent {
@@ -102,6 +124,42 @@ impl Decodable for WampEvent {
                 let message_type = try!(d.read_seq_elt(0, |d| d.read_u32()));
                 let message_type = MessageType::from(message_type);
                 match message_type {
+                    MessageType::WELCOME => {
+                        if len != 3 {
+                            Err(d.error("unexpected len != 3 for WELCOME message"))
+                        } else {
+                            let session_id = try!(d.read_seq_elt(1, |d| d.read_u64()));
+                            let details = try!(d.read_seq_elt(2, |d| WelcomeDetails::decode(d)));
+                            Ok(WampEvent::Welcome {
+                                message_type: message_type,
+                                session_id: session_id,
+                                details: details,
+                            })
+                        }
+                    },
+
+                    MessageType::ABORT | MessageType::GOODBYE => {
+                        if len != 3 {
+                            Err(d.error("unexpected len != 3 for ABORT or GOODBYE message"))
+                        } else {
+                            let details = try!(d.read_seq_elt(1, |d| GoodbyeDetails::decode(d)));
+                            let reason = try!(d.read_seq_elt(2, |d| d.read_str()));
+                            if message_type == MessageType::ABORT {
+                                Ok(WampEvent::Abort {
+                                    message_type: message_type,
+                                    details: details,
+                                    reason: reason,
+                                })
+                            } else {
+                                Ok(WampEvent::Goodbye {
+                                    message_type: message_type,
+                                    details: details,
+                                    reason: reason,
+                                })
+                            }
+                        }
+                    },
+
                     MessageType::SUBSCRIBED => {
                         if len != 3 {
                             Err(d.error("unexpected len != 3 for SUBSCRIBED message"))
@@ -122,8 +180,7 @@ impl Decodable for WampEvent {
                         } else {
                             let topic_id = try!(d.read_seq_elt(1, |d| d.read_u64()));
                             let event_id = try!(d.read_seq_elt(2, |d| d.read_u64()));
-                            //TODO: Actually fulfill these options!
-                            let options = Options::Empty;
+                            let details = try!(d.read_seq_elt(3, |d| EventDetails::decode(d)));
                             let has_kwargs = len == 6;
 
                             // Read positional arguments
@@ -139,11 +196,30 @@ impl Decodable for WampEvent {
                                message_type: message_type, 
                                topic_id: topic_id,
                                event_id: event_id,
-                               options: options,
+                               details: details,
                                has_kwargs: has_kwargs, 
                            })
                         }
-                    }
+                    },
+
+                    MessageType::ERROR => {
+                        if len < 5 || len > 7 {
+                            Err(d.error("unexpected len != {5, 6, 7} for ERROR message"))
+                        } else {
+                            let request_type = try!(d.read_seq_elt(1, |d| d.read_u32()));
+                            let request_id = try!(d.read_seq_elt(2, |d| d.read_u64()));
+                            let details = try!(d.read_seq_elt(3, |d| ErrorDetails::decode(d)));
+                            let uri = try!(d.read_seq_elt(4, |d| d.read_str()));
+
+                            Ok(WampEvent::Error {
+                                message_type: message_type,
+                                request_type: MessageType::from(request_type),
+                                request_id: request_id,
+                                details: details,
+                                uri: uri,
+                            })
+                        }
+                    },
                     _ => Err(d.error(&*format!("protocol violation: no message type {} exists", 
                                                message_type as u32)))
                 }
@@ -155,12 +231,13 @@ impl Decodable for WampEvent {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 /// A struct representing the payload that's received from a WAMP event.
 pub struct Payload {
     args: String,
     kwargs: Option<String>,
+    details: EventDetails,
     serializer: Serializer
 }
 
@@ -215,6 +292,7 @@ impl Payload {
                 return Ok(Payload {
                     args: args.to_string(),
                     kwargs: kwargs,
+                    details: EventDetails::default(),
                     serializer: Serializer::json()
                 });
             }
@@ -224,6 +302,17 @@ impl Payload {
     }
 
 
+    /// Attach the details the router sent along with the event.
+    pub fn with_details(mut self, details: EventDetails) -> Payload {
+        self.details = details;
+        self
+    }
+
+    /// Details of the event, such as the publisher if it chose to disclose itself.
+    pub fn details(&self) -> &EventDetails {
+        &self.details
+    }
+
     #[allow(dead_code)]
     /// Extract positional arguments from the payload.
     pub fn decode_args<T: Decodable>(&self) -> WampResult<T> {
@@ -254,9 +343,53 @@ impl Encodable for MessageType {
     }
 }
 
-/// Generates a new event_id to track messages sent to and from the WAMP Router
-pub fn new_event_id() -> u64 {
-    rand::thread_rng().next_u32() as u64
+/// Builds a `WampError::Remote` from a raw ERROR message, extracting its optional
+/// positional and keyword arguments.
+pub fn remote_error(raw: &str, request_type: MessageType, uri: String) -> WampError {
+    let (args, kwargs) = match Json::from_str(raw) {
+        Ok(Json::Array(mut message)) => {
+            let kwargs = if message.len() > 6 { message.pop() } else { None };
+            let args = match if message.len() > 5 { message.pop() } else { None } {
+                Some(Json::Array(args)) => args,
+                _ => Vec::new(),
+            };
+            (args, kwargs)
+        },
+        _ => (Vec::new(), None),
+    };
+
+    WampError::Remote {
+        request_type: request_type,
+        uri: uri,
+        args: args,
+        kwargs: kwargs,
+    }
+}
+
+/// The largest id, every id is in `[1, 2^53]` so that it is exact as a JSON number
+pub const MAX_ID: u64 = 1 << 53;
+
+/// Generates the ids of the requests of a session in its session scope,
+/// counting up from 1 and starting over after `MAX_ID`
+pub struct RequestIds {
+    last: Mutex<u64>,
+}
+
+impl RequestIds {
+    pub fn new() -> Self {
+        RequestIds { last: Mutex::new(0) }
+    }
+
+    /// The next id for which `in_use` is false, so that it is unique among outstanding requests
+    pub fn next<F: Fn(u64) -> bool>(&self, in_use: F) -> u64 {
+        let mut last = self.last.lock().unwrap();
+        loop {
+            *last = if *last >= MAX_ID { 1 } else { *last + 1 };
+            if !in_use(*last) {
+                return *last;
+            }
+        }
+    }
 }
 
 
@@ -299,10 +432,10 @@ pub struct EventSubscribe {
 }
 
 impl EventSubscribe {
-    pub fn new(topic: String) -> Self {
+    pub fn new(id: u64, topic: String) -> Self {
         EventSubscribe {
             message_type: MessageType::SUBSCRIBE,
-            id: new_event_id(),
+            id: id,
             topic: topic,
             options: Options::Empty
         }
@@ -352,6 +485,43 @@ impl Encodable for EventJoin {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct EventGoodbye {
+    message_type: MessageType,
+    options: Options,
+    reason: String,
+}
+
+impl EventGoodbye {
+    pub fn new(reason: &str) -> Self {
+        EventGoodbye {
+            message_type: MessageType::GOODBYE,
+            options: Options::Empty,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// An ABORT, which looks just like a GOODBYE on the wire
+    pub fn abort(reason: &str) -> Self {
+        EventGoodbye {
+            message_type: MessageType::ABORT,
+            options: Options::Empty,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Encodable for EventGoodbye {
+    fn encode<S: Encoder>(&self, s: &mut S) -> result::Result<(), S::Error> {
+        s.emit_seq(3, |s| {
+            try!(s.emit_seq_elt(0, |s| self.message_type.encode(s)));
+            try!(s.emit_seq_elt(1, |s| self.options.encode(s)));
+            try!(s.emit_seq_elt(2, |s| self.reason.encode(s)));
+            Ok(())
+        })
+    }
+}
+
 macro_rules! wamp_type {
     ($($t:ident),+) => {
         /// All types that can be sent to/from a WAMP Router
@@ -424,3 +594,102 @@ fn message_extract_payload() {
     let test_struct: TestStruct= payload2.unwrap().decode_kwargs().unwrap();
     assert!(test_struct == TestStruct{field: 42, binary: false, word: "hello world".to_string()});
 }
+
+#[test]
+fn message_event_publisher_details() {
+    let raw = "[36,5512315355,4429313566,{\"publisher\":3335656,\"publisher_authrole\":\"user\"},[42]]";
+    let event : WampEvent = Serializer::json().decode(raw).unwrap();
+    match event {
+        WampEvent::Event { details, has_kwargs, .. } => {
+            assert!(details.publisher == Some(3335656));
+            assert!(details.publisher_authid == None);
+            assert!(details.publisher_authrole == Some("user".to_string()));
+            assert!(!has_kwargs);
+        },
+        _ => panic!("expected an EVENT"),
+    }
+}
+
+#[test]
+fn message_publish_disclose_me() {
+    use rustc_serialize::json;
+    use options::PublishOptions;
+
+    let msg = EventPublish {
+        message_type: MessageType::PUBLISH,
+        id: 1,
+        options: Options::Publish(PublishOptions { disclose_me: true }),
+        topic: "com.myapp.topic1".to_string(),
+        args: vec![WampType::i32(42)],
+        kwargs: WampType::None,
+    };
+    assert!(json::encode(&msg).unwrap() == "[16,1,{\"disclose_me\":true},\"com.myapp.topic1\",[42],{}]");
+
+    let msg = EventPublish { options: Options::Publish(PublishOptions::default()), .. msg };
+    assert!(json::encode(&msg).unwrap() == "[16,1,{},\"com.myapp.topic1\",[42],{}]");
+}
+
+#[test]
+fn message_decode_error() {
+    let raw = "[8,32,713845233,{},\"wamp.error.not_authorized\",[\"denied\"],{\"topic\":\"com.myapp.topic1\"}]";
+    let event : WampEvent = Serializer::json().decode(raw).unwrap();
+    let (request_type, uri) = match event {
+        WampEvent::Error { request_type, request_id, uri, .. } => {
+            assert!(request_id == 713845233);
+            (request_type, uri)
+        },
+        _ => panic!("expected an ERROR"),
+    };
+    assert!(request_type == MessageType::SUBSCRIBE);
+    assert!(uri == ::uri::NOT_AUTHORIZED);
+
+    match remote_error(raw, request_type, uri) {
+        WampError::Remote { args, kwargs, .. } => {
+            assert!(args == vec![Json::String("denied".to_string())]);
+            let kwargs = kwargs.unwrap();
+            assert!(kwargs.find("topic") == Some(&Json::String("com.myapp.topic1".to_string())));
+        },
+        _ => panic!("expected a remote error"),
+    }
+
+    let raw = "[8,32,713845234,{},\"wamp.error.invalid_uri\"]";
+    match Serializer::json().decode::<WampEvent>(raw).unwrap() {
+        WampEvent::Error { request_type, uri, .. } => match remote_error(raw, request_type, uri) {
+            WampError::Remote { args, kwargs, .. } => assert!(args.is_empty() && kwargs.is_none()),
+            _ => panic!("expected a remote error"),
+        },
+        _ => panic!("expected an ERROR"),
+    }
+}
+
+#[test]
+fn message_decode_goodbye() {
+    let raw = "[6,{\"message\":\"The host is shutting down now.\"},\"wamp.close.system_shutdown\"]";
+    match Serializer::json().decode::<WampEvent>(raw).unwrap() {
+        WampEvent::Goodbye { details, reason, .. } => {
+            assert!(reason == ::uri::SYSTEM_SHUTDOWN);
+            assert!(details.message == Some("The host is shutting down now.".to_string()));
+        },
+        _ => panic!("expected a GOODBYE"),
+    }
+
+    let raw = "[3,{},\"wamp.error.no_such_realm\"]";
+    match Serializer::json().decode::<WampEvent>(raw).unwrap() {
+        WampEvent::Abort { details, reason, .. } => {
+            assert!(reason == ::uri::NO_SUCH_REALM);
+            assert!(details.message == None);
+        },
+        _ => panic!("expected an ABORT"),
+    }
+}
+
+#[test]
+fn message_request_ids() {
+    let ids = RequestIds::new();
+    assert!(ids.next(|_| false) == 1);
+    assert!(ids.next(|id| id == 2 || id == 3) == 4);
+
+    *ids.last.lock().unwrap() = MAX_ID - 1;
+    assert!(ids.next(|_| false) == MAX_ID);
+    assert!(ids.next(|id| id == 1) == 2);
+}
@@ -5,16 +5,70 @@ use rustc_serialize::{Encodable, Encoder};
 pub enum Options {
     // TODO: // different options
     Empty,
+    Publish(PublishOptions),
 }
 
 impl Encodable for Options {
     fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
         match self {
-           &Options::Empty => s.emit_map(0, |_| Ok(())) 
+           &Options::Empty => s.emit_map(0, |_| Ok(())),
+           &Options::Publish(ref options) => options.encode(s),
         }
     }
 }
 
+/// Options that can be attached to a PUBLISH event
+#[derive(RustcDecodable, Debug, Clone, PartialEq, Default)]
+pub struct PublishOptions {
+    /// Ask the router to disclose our session id to the subscribers of this event
+    pub disclose_me: bool,
+}
+
+impl Encodable for PublishOptions {
+    fn encode<S: Encoder>(&self, s: &mut S) -> Result<(), S::Error> {
+        // Only options that differ from the WAMP defaults are sent
+        let len = if self.disclose_me { 1 } else { 0 };
+        s.emit_map(len, |s| {
+            if self.disclose_me {
+                try!(s.emit_map_elt_key(0, |s| "disclose_me".encode(s)));
+                try!(s.emit_map_elt_val(0, |s| true.encode(s)));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Details sent by the router along with an EVENT
+#[derive(RustcDecodable, Debug, Clone, PartialEq, Default)]
+pub struct EventDetails {
+    /// The session id of the publisher, only present if the publisher disclosed itself
+    pub publisher: Option<u64>,
+    /// The authentication id of the publisher, if disclosed
+    pub publisher_authid: Option<String>,
+    /// The authentication role of the publisher, if disclosed
+    pub publisher_authrole: Option<String>,
+}
+
+/// Details sent by the router along with a WELCOME
+#[derive(RustcDecodable, Debug, Clone, PartialEq, Default)]
+pub struct WelcomeDetails {
+    /// The authentication id the session was given
+    pub authid: Option<String>,
+    /// The authentication role the session was given
+    pub authrole: Option<String>,
+}
+
+/// Details sent by the router along with a GOODBYE or ABORT
+#[derive(RustcDecodable, Debug, Clone, PartialEq, Default)]
+pub struct GoodbyeDetails {
+    /// A human readable explanation of why the session is closed
+    pub message: Option<String>,
+}
+
+/// Details sent by the router along with an ERROR
+#[derive(RustcDecodable, Debug, Clone, PartialEq, Default)]
+pub struct ErrorDetails {}
+
 /// Represents advanced features this WAMP Client implements
 #[derive(RustcDecodable, RustcEncodable, Debug, Clone)]
 pub struct Details { 
@@ -99,14 +153,14 @@ impl Details {
                 },
                 publisher: Publisher {
                     features: PublisherFeatures {
-                        publisher_identification: false,
+                        publisher_identification: true,
                         subscriber_blackwhite_listing: false,
                         publisher_exclusion: false,
                     }
                 },
                 subscriber: Subscriber {
                     features: SubscriberFeatures {
-                       publisher_identification: false,
+                       publisher_identification: true,
                        pattern_based_subscription: false,
                        subscription_revocation: false,
                     }
@@ -0,0 +1,146 @@
+use std::cmp;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Condvar};
+use std::time::{Duration, Instant};
+
+use message::Payload;
+use options::EventDetails;
+
+/// What an `EventStream` does with a new event when its buffer is full
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Overflow {
+    /// Discard the oldest buffered event to make room
+    DropOldest,
+    /// Discard the new event
+    DropNewest,
+    /// Wait until the stream is read. This blocks the thread the callbacks run on, see `Dispatch`:
+    /// with `Dispatch::Inline` every other subscription of the session waits as well,
+    /// with `Dispatch::Pool` those sharing the worker thread do.
+    Block,
+}
+
+struct Buffer {
+    events: VecDeque<(EventDetails, Payload)>,
+    /// Set when the subscription is gone, e.g. because the session was dropped
+    closed: bool,
+    /// Set when the `EventStream` was dropped
+    dropped: bool,
+}
+
+type Shared = Arc<(Mutex<Buffer>, Condvar)>;
+
+/// The events published to a topic, see `Session::subscribe_stream`
+///
+/// Iterating blocks until the next event arrives and ends once the session is dropped.
+/// Dropping the stream removes it from the subscription.
+pub struct EventStream {
+    shared: Shared,
+    /// Called when the stream is dropped
+    on_drop: Option<Box<Fn() + Send + Sync>>,
+}
+
+/// The end of an `EventStream` that the subscription pushes events into
+pub struct EventSink {
+    shared: Shared,
+    capacity: usize,
+    overflow: Overflow,
+}
+
+/// Creates a stream buffering at most `capacity` events (at least one), and the sink that feeds it
+pub fn event_stream(capacity: usize, overflow: Overflow) -> (EventSink, EventStream) {
+    let shared = Arc::new((Mutex::new(Buffer { events: VecDeque::new(), closed: false, dropped: false }), Condvar::new()));
+    let sink = EventSink { shared: shared.clone(), capacity: cmp::max(capacity, 1), overflow: overflow };
+    (sink, EventStream { shared: shared, on_drop: None })
+}
+
+/// Calls `on_drop` once `stream` is dropped, not a method so that it stays out of the public API
+pub fn on_drop(stream: &mut EventStream, on_drop: Box<Fn() + Send + Sync>) {
+    stream.on_drop = Some(on_drop);
+}
+
+impl EventSink {
+    pub fn push(&self, payload: &Payload) {
+        let &(ref lock, ref condvar) = &*self.shared;
+        let mut buffer = lock.lock().unwrap();
+        if buffer.dropped {
+            return;
+        }
+        if buffer.events.len() >= self.capacity {
+            match self.overflow {
+                Overflow::DropOldest => { buffer.events.pop_front(); },
+                Overflow::DropNewest => return,
+                Overflow::Block => while buffer.events.len() >= self.capacity && !buffer.dropped {
+                    buffer = condvar.wait(buffer).unwrap();
+                },
+            }
+        }
+        buffer.events.push_back((payload.details().clone(), payload.clone()));
+        condvar.notify_all();
+    }
+}
+
+impl Drop for EventSink {
+    fn drop(&mut self) {
+        let &(ref lock, ref condvar) = &*self.shared;
+        lock.lock().unwrap().closed = true;
+        condvar.notify_all();
+    }
+}
+
+impl EventStream {
+    /// The next event, `None` if none arrived within `timeout` or the stream ended
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<(EventDetails, Payload)> {
+        let deadline = Instant::now() + timeout;
+        let &(ref lock, ref condvar) = &*self.shared;
+        let mut buffer = lock.lock().unwrap();
+        loop {
+            if let Some(event) = buffer.events.pop_front() {
+                condvar.notify_all();
+                return Some(event);
+            }
+            let now = Instant::now();
+            if buffer.closed || now >= deadline {
+                return None;
+            }
+            buffer = condvar.wait_timeout(buffer, deadline - now).unwrap().0;
+        }
+    }
+
+    /// The next event if one is buffered, without waiting
+    pub fn try_next(&mut self) -> Option<(EventDetails, Payload)> {
+        let &(ref lock, ref condvar) = &*self.shared;
+        let event = lock.lock().unwrap().events.pop_front();
+        condvar.notify_all();
+        event
+    }
+}
+
+impl Iterator for EventStream {
+    type Item = (EventDetails, Payload);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let &(ref lock, ref condvar) = &*self.shared;
+        let mut buffer = lock.lock().unwrap();
+        loop {
+            if let Some(event) = buffer.events.pop_front() {
+                condvar.notify_all();
+                return Some(event);
+            }
+            if buffer.closed {
+                return None;
+            }
+            buffer = condvar.wait(buffer).unwrap();
+        }
+    }
+}
+
+impl Drop for EventStream {
+    fn drop(&mut self) {
+        let &(ref lock, ref condvar) = &*self.shared;
+        lock.lock().unwrap().dropped = true;
+        condvar.notify_all();
+        if let Some(ref on_drop) = self.on_drop {
+            on_drop();
+        }
+    }
+}
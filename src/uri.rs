@@ -0,0 +1,179 @@
+//! WAMP URIs, including the error URIs predefined by the specification.
+
+use std::fmt;
+
+use WampError;
+use WampResult;
+
+/// How strictly the components of a URI are checked
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum UriCheck {
+    /// Components may only contain lower-case letters, digits and `_`
+    Strict,
+    /// Components may contain anything but whitespace, `.` and `#`
+    Loose,
+}
+
+/// A validated WAMP URI, used for realms, topics and procedures.
+///
+/// # Examples
+///
+/// ```
+/// use rump::uri::{Uri, UriCheck};
+///
+/// assert!(Uri::strict("com.myapp.topic1").is_ok());
+/// assert!(Uri::strict("com.myApp.topic1").is_err());
+/// assert!(Uri::loose("com.myApp.topic1").is_ok());
+///
+/// // Pattern-based subscriptions may leave components empty
+/// assert!(Uri::wildcard("com.myapp..update", UriCheck::Strict).is_ok());
+/// // or match every URI starting with the pattern
+/// assert!(Uri::prefix("com.myapp.", UriCheck::Strict).is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Uri(String);
+
+/// Which components of a URI may be empty
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Form {
+    /// None of them
+    Exact,
+    /// Any of them, see `Uri::wildcard`
+    Wildcard,
+    /// Only the last one, see `Uri::prefix`
+    Prefix,
+}
+
+impl Uri {
+    /// Validate a URI with strict rules, which all WAMP peers understand
+    pub fn strict(uri: &str) -> WampResult<Uri> {
+        Self::validate(uri, UriCheck::Strict, Form::Exact)
+    }
+
+    /// Validate a URI with loose rules, the minimum a router must accept
+    pub fn loose(uri: &str) -> WampResult<Uri> {
+        Self::validate(uri, UriCheck::Loose, Form::Exact)
+    }
+
+    /// Validate a URI pattern used for wildcard-matching, where empty components match
+    /// anything, e.g. `com.myapp..update`
+    pub fn wildcard(uri: &str, check: UriCheck) -> WampResult<Uri> {
+        Self::validate(uri, check, Form::Wildcard)
+    }
+
+    /// Validate a URI pattern used for prefix-matching, which matches every URI starting with it.
+    /// It must be the start of a valid URI, so it may end in the `.` between two components,
+    /// e.g. `com.myapp.` as well as `com.myapp`
+    pub fn prefix(uri: &str, check: UriCheck) -> WampResult<Uri> {
+        Self::validate(uri, check, Form::Prefix)
+    }
+
+    fn validate(uri: &str, check: UriCheck, form: Form) -> WampResult<Uri> {
+        let valid_char = |c: char| match check {
+            UriCheck::Strict => (c >= 'a' && c <= 'z') || (c >= '0' && c <= '9') || c == '_',
+            UriCheck::Loose => !c.is_whitespace() && c != '#',
+        };
+
+        let last = uri.split('.').count() - 1;
+        let valid = !uri.is_empty() && uri.split('.').enumerate().all(|(i, component)| {
+            if component.is_empty() {
+                match form {
+                    Form::Exact => false,
+                    Form::Wildcard => true,
+                    Form::Prefix => i == last && i > 0,
+                }
+            } else {
+                component.chars().all(&valid_char)
+            }
+        });
+
+        if valid {
+            Ok(Uri(uri.to_string()))
+        } else {
+            Err(WampError::InvalidUri(uri.to_string()))
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &*self.0
+    }
+}
+
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The peer provided an incorrect URI for any URI-based attribute of a WAMP message
+pub const INVALID_URI: &'static str = "wamp.error.invalid_uri";
+/// A Dealer could not perform a call, since no procedure is currently registered under the given URI
+pub const NO_SUCH_PROCEDURE: &'static str = "wamp.error.no_such_procedure";
+/// A procedure could not be registered, since a procedure with the given URI is already registered
+pub const PROCEDURE_ALREADY_EXISTS: &'static str = "wamp.error.procedure_already_exists";
+/// A Dealer could not perform an unregister, since the given registration is not active
+pub const NO_SUCH_REGISTRATION: &'static str = "wamp.error.no_such_registration";
+/// A Broker could not perform an unsubscribe, since the given subscription is not active
+pub const NO_SUCH_SUBSCRIPTION: &'static str = "wamp.error.no_such_subscription";
+/// A call failed since the given argument types or values are not acceptable to the called procedure
+pub const INVALID_ARGUMENT: &'static str = "wamp.error.invalid_argument";
+/// The peer is shutting down completely
+pub const SYSTEM_SHUTDOWN: &'static str = "wamp.close.system_shutdown";
+/// The peer wants to leave the realm
+pub const CLOSE_REALM: &'static str = "wamp.close.close_realm";
+/// A peer acknowledges ending of a session
+pub const GOODBYE_AND_OUT: &'static str = "wamp.close.goodbye_and_out";
+/// A peer received a message that violates the WAMP protocol
+pub const PROTOCOL_VIOLATION: &'static str = "wamp.error.protocol_violation";
+/// A join, call, register, publish or subscribe failed because the session is not authorized to perform the operation
+pub const NOT_AUTHORIZED: &'static str = "wamp.error.not_authorized";
+/// A Dealer or Broker could not determine if the session is authorized to perform the operation
+pub const AUTHORIZATION_FAILED: &'static str = "wamp.error.authorization_failed";
+/// A peer tried to join a non-existing realm
+pub const NO_SUCH_REALM: &'static str = "wamp.error.no_such_realm";
+/// A peer was to be authenticated under a role that does not (or no longer) exist on the router
+pub const NO_SUCH_ROLE: &'static str = "wamp.error.no_such_role";
+/// A call or invocation was canceled
+pub const CANCELED: &'static str = "wamp.error.canceled";
+/// The router rejected an option sent with a request
+pub const OPTION_NOT_ALLOWED: &'static str = "wamp.error.option_not_allowed";
+/// The router rejected a request with `disclose_me` set
+pub const OPTION_DISALLOWED_DISCLOSE_ME: &'static str = "wamp.error.option_disallowed.disclose_me";
+/// A Dealer could not find a callee that is eligible for a call
+pub const NO_ELIGIBLE_CALLEE: &'static str = "wamp.error.no_eligible_callee";
+/// The transport to the peer failed
+pub const NETWORK_FAILURE: &'static str = "wamp.error.network_failure";
+
+#[test]
+fn uri_strict() {
+    assert!(Uri::strict("com.myapp.topic1").is_ok());
+    assert!(Uri::strict("com.my_app.topic_2").is_ok());
+    assert!(Uri::strict("").is_err());
+    assert!(Uri::strict("com.myApp.topic1").is_err());
+    assert!(Uri::strict("com.myapp-2.topic1").is_err());
+    assert!(Uri::strict("com..topic1").is_err());
+    assert!(Uri::strict("com.myapp.topic1.").is_err());
+}
+
+#[test]
+fn uri_loose() {
+    assert!(Uri::loose("com.myApp.topic-1").is_ok());
+    assert!(Uri::loose("com.my app.topic1").is_err());
+    assert!(Uri::loose("com.myapp.#topic1").is_err());
+    assert!(Uri::loose(".com.myapp").is_err());
+}
+
+#[test]
+fn uri_patterns() {
+    assert!(Uri::wildcard("com.myapp..update", UriCheck::Strict).is_ok());
+    assert!(Uri::wildcard(".myapp.", UriCheck::Loose).is_ok());
+    assert!(Uri::wildcard("com.my app..update", UriCheck::Loose).is_err());
+    assert!(Uri::wildcard("", UriCheck::Loose).is_err());
+
+    assert!(Uri::prefix("com.myapp", UriCheck::Strict).is_ok());
+    assert!(Uri::prefix("com.myapp.", UriCheck::Strict).is_ok());
+    assert!(Uri::prefix("com.myApp.", UriCheck::Strict).is_err());
+    assert!(Uri::prefix("com..myapp", UriCheck::Loose).is_err());
+    assert!(Uri::prefix(".com", UriCheck::Loose).is_err());
+    assert!(Uri::prefix(".", UriCheck::Loose).is_err());
+}